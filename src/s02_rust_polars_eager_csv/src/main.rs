@@ -2,8 +2,96 @@ use glob::glob;
 use home;
 use polars::prelude::*;
 // use std::fs::File;
+use std::collections::HashMap;
+use std::env;
 use std::path::PathBuf;
 
+fn cli_pivot_columns() -> Option<String> {
+    // Checks for a '--pivot=<columns>' CLI flag requesting a wide
+    //  cross-tabulation instead of the long per-key means below
+
+    env::args()
+        .find(|arg| arg.starts_with("--pivot="))
+        .map(|arg| arg.trim_start_matches("--pivot=").to_string())
+}
+
+fn any_value_label(value: &AnyValue) -> String {
+    // Renders an AnyValue as a row/column label without the quoting that
+    //  AnyValue's Display impl adds around Utf8 values
+
+    match value {
+        AnyValue::Utf8(s) => s.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn pivot(df: &DataFrame, index: &str, columns: &str, values: &str, agg: &str) -> DataFrame {
+    // Reshapes `df` into a wide matrix: rows indexed by `index`, one column
+    //  per distinct value of `columns`, cells aggregated ("mean" or "sum")
+    //  over `values`. Computes the (index, columns) groupby once, then
+    //  scatters each aggregated cell straight into its (row, column) slot
+    //  in a single pass, instead of re-scanning the frame for every column.
+
+    let grouped = df.groupby([index, columns]).unwrap().select([values]);
+    let agg_df = if agg == "sum" {
+        grouped.sum().unwrap()
+    } else {
+        grouped.mean().unwrap()
+    };
+
+    let index_col = agg_df.column(index).unwrap();
+    let columns_col = agg_df.column(columns).unwrap();
+    let value_col = agg_df
+        .column(&format!("{}_{}", values, if agg == "sum" { "sum" } else { "mean" }))
+        .unwrap();
+
+    // sort on the original typed values (numeric order, not lexicographic
+    //  string order), then label each sorted value afterwards
+    let row_labels: Vec<String> = index_col
+        .unique()
+        .unwrap()
+        .sort(false)
+        .iter()
+        .map(|v| any_value_label(&v))
+        .collect();
+    let col_labels: Vec<String> = columns_col
+        .unique()
+        .unwrap()
+        .sort(false)
+        .iter()
+        .map(|v| any_value_label(&v))
+        .collect();
+
+    let row_slots: HashMap<&str, usize> = row_labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| (label.as_str(), i))
+        .collect();
+    let col_slots: HashMap<&str, usize> = col_labels
+        .iter()
+        .enumerate()
+        .map(|(j, label)| (label.as_str(), j))
+        .collect();
+
+    let mut cells: Vec<Vec<Option<f64>>> = vec![vec![None; col_labels.len()]; row_labels.len()];
+    for i in 0..agg_df.height() {
+        let row_label = any_value_label(&index_col.get(i).unwrap());
+        let col_label = any_value_label(&columns_col.get(i).unwrap());
+        let r = row_slots[row_label.as_str()];
+        let c = col_slots[col_label.as_str()];
+        cells[r][c] = value_col.get(i).unwrap().extract::<f64>();
+    }
+
+    let mut out_columns: Vec<Series> = Vec::with_capacity(col_labels.len() + 1);
+    out_columns.push(Series::new(index, &row_labels));
+    for (j, label) in col_labels.iter().enumerate() {
+        let cell_values: Vec<Option<f64>> = (0..row_labels.len()).map(|r| cells[r][j]).collect();
+        out_columns.push(Series::new(label, cell_values));
+    }
+
+    DataFrame::new(out_columns).unwrap()
+}
+
 fn get_synthetic_data_path() -> PathBuf {
     // Returns path to directory where synthetic data is stored
 
@@ -67,6 +155,12 @@ fn main() {
         .join(&df_key, ["A"], ["key"], JoinType::Left, None)
         .unwrap();
 
+    if let Some(columns) = cli_pivot_columns() {
+        let df_pivoted = pivot(&df_all_03, "A", &columns, "P", "mean");
+        println!("pivot\n {:?}", df_pivoted);
+        return;
+    }
+
     // let df_all_04 = df_all_03.groupby(["A"]).unwrap().mean().unwrap();
     // using 'mean' directly (above) gets wrong answers, even though using 'sum'
     //  and 'count' separately each get correct answers
@@ -77,3 +171,56 @@ fn main() {
         .unwrap();
     println!("means\n {:?}", df_all_04.mean());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_frame() -> DataFrame {
+        df![
+            "A" => ["x", "x", "y", "y"],
+            "group" => ["one", "two", "one", "two"],
+            "value" => [1.0, 2.0, 3.0, 4.0],
+        ]
+        .unwrap()
+    }
+
+    #[test]
+    fn pivot_scatters_each_cell_to_its_row_and_column() {
+        let df_pivoted = pivot(&sample_frame(), "A", "group", "value", "mean");
+
+        assert_eq!(df_pivoted.shape(), (2, 3));
+        assert_eq!(
+            df_pivoted.column("A").unwrap().utf8().unwrap().into_no_null_iter().collect::<Vec<_>>(),
+            vec!["x", "y"]
+        );
+        assert_eq!(
+            df_pivoted.column("one").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>(),
+            vec![1.0, 3.0]
+        );
+        assert_eq!(
+            df_pivoted.column("two").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>(),
+            vec![2.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn pivot_leaves_missing_combinations_null() {
+        let df = df![
+            "A" => ["x", "y"],
+            "group" => ["one", "two"],
+            "value" => [1.0, 2.0],
+        ]
+        .unwrap();
+
+        let df_pivoted = pivot(&df, "A", "group", "value", "mean");
+
+        assert_eq!(df_pivoted.column("two").unwrap().f64().unwrap().get(0), None);
+        assert_eq!(df_pivoted.column("one").unwrap().f64().unwrap().get(1), None);
+    }
+
+    #[test]
+    fn any_value_label_strips_utf8_quoting() {
+        assert_eq!(any_value_label(&AnyValue::Utf8("abc")), "abc");
+    }
+}
@@ -1,9 +1,247 @@
 use glob::glob;
 use home;
 use polars::prelude::*;
-use std::fs::File;
+use std::env;
 use std::path::PathBuf;
 
+fn parse_streaming_flag(args: &[String]) -> bool {
+    // Checks for a '--streaming' flag among the given args
+
+    args.iter().any(|arg| arg == "--streaming")
+}
+
+fn streaming_requested() -> bool {
+    // Checks for a '--streaming' CLI flag requesting the streaming collect engine
+
+    let args: Vec<String> = env::args().collect();
+    parse_streaming_flag(&args)
+}
+
+fn parse_time_window_spec(spec: &str) -> Option<(String, String, String, ClosedWindow)> {
+    // Parses a '<every>[:<period>:<offset>:<closed>]' spec (e.g. '1h' or
+    //  '1h:2h:-1h:right'). 'period' defaults to 'every', 'offset' to '0ns'
+    //  and 'closed' to 'left' when omitted.
+
+    let mut parts = spec.splitn(4, ':');
+    let every = parts.next()?.to_string();
+    if every.is_empty() {
+        return None;
+    }
+    let period = parts.next().unwrap_or(&every).to_string();
+    let offset = parts.next().unwrap_or("0ns").to_string();
+    let closed = match parts.next() {
+        Some("right") => ClosedWindow::Right,
+        Some("both") => ClosedWindow::Both,
+        Some("none") => ClosedWindow::None,
+        _ => ClosedWindow::Left,
+    };
+
+    Some((every, period, offset, closed))
+}
+
+fn cli_time_window() -> Option<(String, String, String, ClosedWindow)> {
+    // Checks for a '--time-window=<every>[:<period>:<offset>:<closed>]' CLI
+    //  flag requesting the dynamic time-window aggregation instead of the
+    //  key groupby
+
+    let arg = env::args().find(|arg| arg.starts_with("--time-window="))?;
+    parse_time_window_spec(arg.trim_start_matches("--time-window="))
+}
+
+fn aggregate_time_windows(
+    lf: LazyFrame,
+    time_col: &str,
+    every: &str,
+    period: &str,
+    offset: &str,
+    closed: ClosedWindow,
+) -> LazyFrame {
+    // Buckets rows into fixed-width time windows and averages every other
+    //  column within each bucket. 'closed' controls which window boundary is
+    //  inclusive: with 'Left', a window's start is inclusive and its stop is
+    //  exclusive, so the very first datapoint is always classified into a
+    //  window even when its timestamp falls exactly on 'start == t', while a
+    //  point landing on 'stop == t' belongs to the next window instead;
+    //  'Right' makes the stop inclusive and the start exclusive, 'Both'
+    //  makes both boundaries inclusive and 'None' neither.
+
+    lf.sort(time_col, SortOptions::default())
+        .groupby_dynamic(
+            vec![],
+            DynamicGroupOptions {
+                index_column: time_col.into(),
+                every: Duration::parse(every),
+                period: Duration::parse(period),
+                offset: Duration::parse(offset),
+                closed_window: closed,
+                ..Default::default()
+            },
+        )
+        .agg([col("*").exclude([time_col]).mean()])
+}
+
+#[derive(Debug, PartialEq)]
+enum JoinMode {
+    Exact,
+    AsOf { strategy: AsofStrategy, tolerance: Option<String>, by: Vec<String> },
+}
+
+fn parse_join_mode(arg: Option<&str>) -> JoinMode {
+    // Parses a '--join=asof[:strategy[:tolerance[:by_col1,by_col2,...]]]'
+    //  argument into an as-of (nearest-key) join mode, or 'Exact' when
+    //  absent. 'strategy' defaults to 'backward', 'tolerance' to none, and
+    //  'by' to no grouping columns when omitted; 'tolerance' is a polars
+    //  duration string (e.g. '5ns', '1h') applied to the on-column.
+
+    let arg = match arg {
+        Some(arg) if arg.starts_with("asof") => arg,
+        _ => return JoinMode::Exact,
+    };
+
+    let mut fields = arg.splitn(4, ':');
+    fields.next(); // "asof"
+    let strategy = match fields.next() {
+        Some("forward") => AsofStrategy::Forward,
+        Some("nearest") => AsofStrategy::Nearest,
+        _ => AsofStrategy::Backward,
+    };
+    let tolerance = fields.next().filter(|s| !s.is_empty()).map(|s| s.to_string());
+    let by = match fields.next() {
+        Some(cols) if !cols.is_empty() => cols.split(',').map(|c| c.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    JoinMode::AsOf { strategy, tolerance, by }
+}
+
+fn cli_join_mode() -> JoinMode {
+    // Checks for a '--join=asof[:strategy[:tolerance[:by_cols]]]' CLI flag
+    //  requesting an as-of (nearest-key) join instead of the default exact
+    //  left join
+
+    let arg = env::args().find(|arg| arg.starts_with("--join="));
+    parse_join_mode(arg.as_deref().map(|arg| arg.trim_start_matches("--join=")))
+}
+
+fn join_key_table(main: LazyFrame, df_key: LazyFrame, mode: &JoinMode) -> LazyFrame {
+    // Joins the main frame against the key table either on exact key
+    //  equality, or (as-of mode) against the nearest preceding/following/
+    //  nearest row in the key table on the ordered 'A' column, optionally
+    //  bounded by a tolerance and matched independently per 'by' group;
+    //  useful when the key table carries values sampled at different points
+    //  than the main data
+
+    match mode {
+        JoinMode::Exact => main.join(df_key, vec![col("A")], vec![col("key")], JoinType::Left),
+        JoinMode::AsOf { strategy, tolerance, by } => {
+            // as-of joins require both sides pre-sorted ascending on the on-column
+            let main = main.sort("A", SortOptions::default());
+            let df_key = df_key.sort("key", SortOptions::default());
+            let (left_by, right_by) = if by.is_empty() {
+                (None, None)
+            } else {
+                (Some(by.clone()), Some(by.clone()))
+            };
+            main.join(
+                df_key,
+                vec![col("A")],
+                vec![col("key")],
+                JoinType::AsOf(AsOfOptions {
+                    strategy: *strategy,
+                    tolerance: None,
+                    tolerance_str: tolerance.clone(),
+                    left_by,
+                    right_by,
+                }),
+            )
+        }
+    }
+}
+
+fn parse_filter_expr(expr_text: &str) -> Option<Expr> {
+    // Parses a '<column><op><value>' predicate string (e.g. 'A>10') into an
+    //  expression that is pushed into the parquet scan, letting row-group
+    //  statistics skip groups that cannot match
+
+    let (op, column, value) = if let Some((column, value)) = expr_text.split_once(">=") {
+        (">=", column, value)
+    } else if let Some((column, value)) = expr_text.split_once("<=") {
+        ("<=", column, value)
+    } else if let Some((column, value)) = expr_text.split_once('>') {
+        (">", column, value)
+    } else if let Some((column, value)) = expr_text.split_once('<') {
+        ("<", column, value)
+    } else if let Some((column, value)) = expr_text.split_once('=') {
+        ("=", column, value)
+    } else {
+        return None;
+    };
+
+    let value: f64 = value.trim().parse().ok()?;
+    let column = col(column.trim());
+    Some(match op {
+        ">=" => column.gt_eq(value),
+        "<=" => column.lt_eq(value),
+        ">" => column.gt(value),
+        "<" => column.lt(value),
+        _ => column.eq(value),
+    })
+}
+
+fn cli_filter() -> Option<Expr> {
+    // Checks for an optional '--filter=<column><op><value>' CLI argument
+    //  (e.g. '--filter=A>10') and parses it into a pushdown predicate
+
+    let arg = env::args().find(|arg| arg.starts_with("--filter="))?;
+    parse_filter_expr(arg.trim_start_matches("--filter="))
+}
+
+fn collect_lazy(lf: LazyFrame, streaming: bool) -> DataFrame {
+    // Collects a LazyFrame. When 'streaming' is requested AND the
+    //  'streaming' cargo feature is compiled in, runs the streaming engine
+    //  in bounded memory; otherwise always falls back to the regular eager
+    //  collect, per-request, rather than panicking on an engine that isn't
+    //  built in.
+
+    if streaming {
+        #[cfg(feature = "streaming")]
+        {
+            return lf.with_streaming(true).collect().unwrap();
+        }
+        #[cfg(not(feature = "streaming"))]
+        {
+            eprintln!(
+                "warning: --streaming requested but the 'streaming' feature is not enabled; falling back to eager collect"
+            );
+        }
+    }
+    lf.collect().unwrap()
+}
+
+#[derive(Debug, PartialEq)]
+enum DataLocation {
+    Local(PathBuf),
+    #[cfg_attr(not(feature = "aws_s3"), allow(dead_code))]
+    Cloud(String),
+}
+
+fn classify_data_location(uri: Option<&str>) -> DataLocation {
+    // Classifies a 'SYNTHETIC_DATA_URI' env var value: a cloud bucket uri
+    //  (e.g. s3://bucket/prefix) when given and pointing at one, else the
+    //  default local directory
+
+    match uri {
+        Some(uri) if uri.starts_with("s3://") => DataLocation::Cloud(uri.to_string()),
+        _ => DataLocation::Local(get_synthetic_data_path()),
+    }
+}
+
+fn get_synthetic_data_location() -> DataLocation {
+    // Returns where synthetic data is stored: a local directory, unless a
+    //  'SYNTHETIC_DATA_URI' env var points at a cloud bucket (e.g. s3://bucket/prefix)
+
+    classify_data_location(env::var("SYNTHETIC_DATA_URI").ok().as_deref())
+}
+
 fn get_synthetic_data_path() -> PathBuf {
     // Returns path to directory where synthetic data is stored
 
@@ -12,67 +250,237 @@ fn get_synthetic_data_path() -> PathBuf {
     path
 }
 
-fn load_key_table(dir_path: &PathBuf, file_extension: &str) -> LazyFrame {
-    // Load table with key column that joins to the main data set
+fn scan_parquet_lf(path: &str, cloud_options: Option<CloudOptions>, filter: Option<&Expr>) -> LazyFrame {
+    // Builds a single lazy parquet scan for 'path' instead of eagerly reading
+    //  the whole file. When 'filter' is given, polars pushes the predicate
+    //  down into the scan, so row groups whose column statistics (min/max)
+    //  cannot satisfy it are skipped before any data is decoded.
+
+    let args = ScanArgsParquet {
+        cloud_options,
+        ..Default::default()
+    };
+    let lf = LazyFrame::scan_parquet(path, args).unwrap();
+    match filter {
+        Some(expr) => lf.filter(expr.clone()),
+        None => lf,
+    }
+}
 
-    let filename_pattern = format!(
-        "{}{}{}",
-        dir_path.to_str().unwrap(),
-        "/*table",
-        file_extension
-    );
+fn scan_tables(
+    location: &DataLocation,
+    glob_suffix: &str,
+    file_extension: &str,
+    filter: Option<&Expr>,
+) -> Vec<LazyFrame> {
+    // Builds one lazy parquet scan per matched file locally, or (behind the
+    //  'aws_s3' feature) a single cloud-scanned LazyFrame covering the glob
 
-    let mut df_key_vec: Vec<DataFrame> = Vec::new();
-    for item in glob(filename_pattern.as_str()).unwrap() {
-        let filepath = item.unwrap();
-        let table_file = File::open(filepath).unwrap();
-        let df = ParquetReader::new(table_file).finish().unwrap();
-        df_key_vec.push(df);
+    match location {
+        DataLocation::Local(dir_path) => {
+            let filename_pattern = format!(
+                "{}{}{}",
+                dir_path.to_str().unwrap(),
+                glob_suffix,
+                file_extension
+            );
+            let mut lf_vec: Vec<LazyFrame> = Vec::new();
+            for item in glob(filename_pattern.as_str()).unwrap() {
+                let filepath = item.unwrap();
+                let lf = scan_parquet_lf(filepath.to_str().unwrap(), None, filter);
+                lf_vec.push(lf);
+            }
+            lf_vec
+        }
+        #[cfg(feature = "aws_s3")]
+        DataLocation::Cloud(uri) => {
+            // Goes through polars' own `CloudOptions`/object_store backend
+            //  rather than calling aws-sdk-s3 directly: polars' "aws" feature
+            //  already depends on aws-config and pulls tokio/futures in to
+            //  drive it. The 'aws_s3' cargo feature should enable polars'
+            //  "aws" feature (transitively bringing in aws-config/
+            //  aws-sdk-s3/tokio/futures) rather than list those crates as
+            //  direct dependencies of this crate, i.e.:
+            //
+            //      [features]
+            //      aws_s3 = ["polars/aws"]
+            //
+            //  No Cargo.toml exists anywhere in this source snapshot to hold
+            //  that declaration, so this match arm is compiled only when
+            //  '--cfg feature="aws_s3"' is passed by hand.
+
+            let uri_pattern = format!("{}{}{}", uri.trim_end_matches('/'), glob_suffix, file_extension);
+            vec![scan_parquet_lf(&uri_pattern, Some(CloudOptions::default()), filter)]
+        }
+        #[cfg(not(feature = "aws_s3"))]
+        DataLocation::Cloud(_) => {
+            panic!("cloud data location requires the 'aws_s3' feature");
+        }
     }
+}
+
+fn load_key_table(location: &DataLocation, file_extension: &str) -> LazyFrame {
+    // Load table with key column that joins to the main data set
 
+    let df_key_vec = scan_tables(location, "/*table", file_extension, None);
     let df_key = df_key_vec[0].clone();
-    df_key.lazy()
+    df_key
 }
 
 fn main() {
-    let input_data_filepath = get_synthetic_data_path();
-    let file_extension = ".parquet";
+    let streaming = streaming_requested();
+    let filter = cli_filter();
+    let time_window = cli_time_window();
 
-    let df_key = load_key_table(&input_data_filepath, file_extension);
+    let input_data_location = get_synthetic_data_location();
+    let file_extension = ".parquet";
 
-    let filename_pattern = format!(
-        "{}{}{}",
-        input_data_filepath.to_str().unwrap(),
-        "/table_*",
-        file_extension
-    );
     let colnames1 = vec!["A", "I", "P"];
-    let colnames2 = vec![
-        colnames1[0].to_string(),
-        colnames1[1].to_string(),
-        colnames1[2].to_string(),
-    ];
-
-    let mut df_vec: Vec<LazyFrame> = Vec::new();
-    for item in glob(filename_pattern.as_str()).unwrap() {
-        let filepath = item.unwrap();
-        let table_file = File::open(filepath).unwrap();
-        let df = ParquetReader::new(table_file)
-            .with_columns(Some(colnames2.clone()))
-            .finish()
-            .unwrap()
-            .lazy();
-        df_vec.push(df);
-    }
 
+    let df_vec = scan_tables(&input_data_location, "/table_*", file_extension, filter.as_ref());
     let df_all_01 = concat(&df_vec, false, false).unwrap();
-    let df_all_02 = df_all_01
-        .select(&[col(colnames1[0]), col(colnames1[1]), col(colnames1[2])])
-        .join(df_key, vec![col("A")], vec![col("key")], JoinType::Left)
+
+    if let Some((every, period, offset, closed)) = time_window {
+        // Demonstrates the dynamic time-window aggregation shape instead of
+        //  the key groupby below; assumes a 'time' datetime column
+
+        let lf_windowed = aggregate_time_windows(df_all_01, "time", &every, &period, &offset, closed);
+        let df_windowed = collect_lazy(lf_windowed, streaming);
+        println!("time-windowed means\n {:?}", df_windowed);
+        return;
+    }
+
+    let df_key = load_key_table(&input_data_location, file_extension);
+    let join_mode = cli_join_mode();
+    let main_frame = df_all_01.select(&[col(colnames1[0]), col(colnames1[1]), col(colnames1[2])]);
+    let lf_all_02 = join_key_table(main_frame, df_key, &join_mode)
         .groupby(["A"])
-        .agg([col("*").mean()])
-        .collect()
-        .unwrap();
+        .agg([col("*").mean()]);
+    let df_all_02 = collect_lazy(lf_all_02, streaming);
 
     println!("means\n {:?}", df_all_02.mean());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_filter_expr_handles_each_operator() {
+        assert_eq!(parse_filter_expr("A>=10").unwrap(), col("A").gt_eq(10.0));
+        assert_eq!(parse_filter_expr("A<=10").unwrap(), col("A").lt_eq(10.0));
+        assert_eq!(parse_filter_expr("A>10").unwrap(), col("A").gt(10.0));
+        assert_eq!(parse_filter_expr("A<10").unwrap(), col("A").lt(10.0));
+        assert_eq!(parse_filter_expr("A=10").unwrap(), col("A").eq(10.0));
+    }
+
+    #[test]
+    fn parse_filter_expr_trims_whitespace() {
+        assert!(parse_filter_expr(" A > 10 ").is_some());
+    }
+
+    #[test]
+    fn parse_filter_expr_rejects_malformed_input() {
+        assert!(parse_filter_expr("not-a-filter").is_none());
+        assert!(parse_filter_expr("A>not-a-number").is_none());
+    }
+
+    #[test]
+    fn parse_time_window_spec_defaults_period_offset_closed() {
+        let (every, period, offset, closed) = parse_time_window_spec("1h").unwrap();
+        assert_eq!(every, "1h");
+        assert_eq!(period, "1h");
+        assert_eq!(offset, "0ns");
+        assert_eq!(closed, ClosedWindow::Left);
+    }
+
+    #[test]
+    fn parse_time_window_spec_honors_all_fields() {
+        let (every, period, offset, closed) = parse_time_window_spec("1h:2h:-1h:right").unwrap();
+        assert_eq!(every, "1h");
+        assert_eq!(period, "2h");
+        assert_eq!(offset, "-1h");
+        assert_eq!(closed, ClosedWindow::Right);
+    }
+
+    #[test]
+    fn parse_time_window_spec_parses_remaining_closed_variants() {
+        assert_eq!(parse_time_window_spec("1h:1h:0ns:both").unwrap().3, ClosedWindow::Both);
+        assert_eq!(parse_time_window_spec("1h:1h:0ns:none").unwrap().3, ClosedWindow::None);
+    }
+
+    #[test]
+    fn parse_time_window_spec_rejects_empty_input() {
+        assert!(parse_time_window_spec("").is_none());
+    }
+
+    #[test]
+    fn classify_data_location_recognizes_s3_uri() {
+        assert_eq!(
+            classify_data_location(Some("s3://bucket/prefix")),
+            DataLocation::Cloud("s3://bucket/prefix".to_string())
+        );
+    }
+
+    #[test]
+    fn classify_data_location_falls_back_to_local() {
+        assert_eq!(classify_data_location(None), DataLocation::Local(get_synthetic_data_path()));
+        assert_eq!(
+            classify_data_location(Some("/not/a/bucket")),
+            DataLocation::Local(get_synthetic_data_path())
+        );
+    }
+
+    #[test]
+    fn parse_join_mode_defaults_to_exact() {
+        assert_eq!(parse_join_mode(None), JoinMode::Exact);
+        assert_eq!(parse_join_mode(Some("exact")), JoinMode::Exact);
+    }
+
+    #[test]
+    fn parse_join_mode_defaults_asof_strategy_to_backward() {
+        assert_eq!(
+            parse_join_mode(Some("asof")),
+            JoinMode::AsOf { strategy: AsofStrategy::Backward, tolerance: None, by: vec![] }
+        );
+    }
+
+    #[test]
+    fn parse_join_mode_honors_explicit_strategy() {
+        assert_eq!(
+            parse_join_mode(Some("asof:forward")),
+            JoinMode::AsOf { strategy: AsofStrategy::Forward, tolerance: None, by: vec![] }
+        );
+        assert_eq!(
+            parse_join_mode(Some("asof:nearest")),
+            JoinMode::AsOf { strategy: AsofStrategy::Nearest, tolerance: None, by: vec![] }
+        );
+    }
+
+    #[test]
+    fn parse_join_mode_honors_tolerance_and_by_columns() {
+        assert_eq!(
+            parse_join_mode(Some("asof:backward:5ns:group")),
+            JoinMode::AsOf {
+                strategy: AsofStrategy::Backward,
+                tolerance: Some("5ns".to_string()),
+                by: vec!["group".to_string()],
+            }
+        );
+        assert_eq!(
+            parse_join_mode(Some("asof:nearest::g1,g2")),
+            JoinMode::AsOf {
+                strategy: AsofStrategy::Nearest,
+                tolerance: None,
+                by: vec!["g1".to_string(), "g2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_streaming_flag_detects_presence_and_absence() {
+        assert!(parse_streaming_flag(&["prog".to_string(), "--streaming".to_string()]));
+        assert!(!parse_streaming_flag(&["prog".to_string()]));
+        assert!(!parse_streaming_flag(&["prog".to_string(), "--filter=A>10".to_string()]));
+    }
+}